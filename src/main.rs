@@ -5,14 +5,16 @@ use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use regex::Regex;
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
 use reqwest::{Client, Url};
-use serde::Deserialize;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 use tracing::{debug, error, info, trace, warn};
 use tracing_subscriber::EnvFilter;
 use clap::ArgAction;
 
 #[derive(Parser, Debug)]
-#[command(name = "es-retention", version, about = "Delete old monthly indices by name (YYYY-MM or YYYY.MM)")]
+#[command(name = "es-retention", version, about = "Delete old indices by name (monthly YYYY-MM/YYYY.MM or daily YYYY-MM-DD/YYYY.MM.DD)")]
 struct Args {
     #[arg(long = "url")]
     url: String,
@@ -24,6 +26,48 @@ struct Args {
     index_prefix: String,
     #[arg(long = "older-than", default_value = "25m")]
     older_than: String,
+    /// Always keep the N newest matching indices, regardless of age.
+    #[arg(long = "keep-last")]
+    keep_last: Option<usize>,
+    /// Keep one index per calendar month, for the N most recent distinct months.
+    #[arg(long = "keep-monthly")]
+    keep_monthly: Option<usize>,
+    /// Keep one index per calendar year, for the N most recent distinct years.
+    #[arg(long = "keep-yearly")]
+    keep_yearly: Option<usize>,
+    /// Keep every index younger than this many months (e.g. '12m'; unlike --older-than, day
+    /// units are not accepted).
+    #[arg(long = "keep-within")]
+    keep_within: Option<String>,
+    /// Keep one index per calendar day, for the N most recent distinct days (requires --date-pattern daily).
+    #[arg(long = "keep-daily")]
+    keep_daily: Option<usize>,
+    /// Keep one index per ISO week, for the N most recent distinct weeks (requires --date-pattern daily).
+    #[arg(long = "keep-weekly")]
+    keep_weekly: Option<usize>,
+    /// Granularity of the date embedded in index names.
+    #[arg(long = "date-pattern", value_enum, default_value = "monthly")]
+    date_pattern: DatePattern,
+    /// Regex with a capture group extracting a stream key (e.g. the non-date prefix) so
+    /// retention is evaluated independently per matching group instead of across the whole
+    /// --index-prefix pool.
+    #[arg(long = "group-by")]
+    group_by: Option<String>,
+    /// Snapshot repository name. When set, a snapshot of all target indices is taken (and
+    /// verified) before any of them are deleted.
+    #[arg(long = "snapshot-repo")]
+    snapshot_repo: Option<String>,
+    /// Timeout in seconds for the snapshot PUT request (with `?wait_for_completion=true`, it
+    /// blocks until the snapshot finishes, which routinely takes far longer than the 30s
+    /// timeout used for the CAT/DELETE calls).
+    #[arg(long = "snapshot-timeout-secs", default_value_t = 1800)]
+    snapshot_timeout_secs: u64,
+    /// Number of DELETE requests to run concurrently in live mode.
+    #[arg(long = "concurrency", default_value_t = 4)]
+    concurrency: usize,
+    /// Summary format printed after a live run.
+    #[arg(long = "output", value_enum, default_value = "text")]
+    output: OutputFormat,
     #[arg(long = "no-dryrun", action = ArgAction::SetTrue)]
     no_dryrun: bool,
 }
@@ -33,6 +77,91 @@ struct CatIndex {
     index: String,
 }
 
+#[derive(Deserialize)]
+struct SnapshotResponse {
+    snapshot: SnapshotInfo,
+}
+
+#[derive(Deserialize)]
+struct SnapshotInfo {
+    state: String,
+}
+
+/// Output format for the post-run deletion summary.
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Outcome of a single index's DELETE request, used both for logging and for the
+/// `--output json` machine-readable summary.
+#[derive(Serialize)]
+struct DeleteOutcome {
+    index: String,
+    success: bool,
+    status: u16,
+    error: Option<String>,
+}
+
+/// Granularity of the date suffix embedded in index names.
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq, Eq)]
+enum DatePattern {
+    /// `prefix-YYYY-MM` / `prefix-YYYY.MM`
+    Monthly,
+    /// `prefix-YYYY-MM-DD` / `prefix-YYYY.MM.DD`
+    Daily,
+}
+
+/// A restic/`forget`-style retention policy: an index is kept if ANY active rule keeps it,
+/// everything else is forgotten (deleted).
+#[derive(Debug, Default)]
+struct RetentionPolicy {
+    keep_last: Option<usize>,
+    keep_daily: Option<usize>,
+    keep_weekly: Option<usize>,
+    keep_monthly: Option<usize>,
+    keep_yearly: Option<usize>,
+    keep_within_months: Option<i32>,
+}
+
+impl RetentionPolicy {
+    fn from_args(args: &Args) -> Result<Self> {
+        if (args.keep_daily.is_some() || args.keep_weekly.is_some())
+            && !matches!(args.date_pattern, DatePattern::Daily)
+        {
+            return Err(anyhow!(
+                "--keep-daily and --keep-weekly bucket by calendar day/ISO week, which needs \
+                 --date-pattern daily; with the default monthly pattern every index in a month \
+                 parses to the same day-1 date and the buckets silently collapse to one per month."
+            ));
+        }
+        let keep_within_months = args
+            .keep_within
+            .as_deref()
+            .map(parse_months)
+            .transpose()
+            .with_context(|| format!("Failed to parse --keep-within='{}'", args.keep_within.as_deref().unwrap_or_default()))?;
+        Ok(Self {
+            keep_last: args.keep_last,
+            keep_daily: args.keep_daily,
+            keep_weekly: args.keep_weekly,
+            keep_monthly: args.keep_monthly,
+            keep_yearly: args.keep_yearly,
+            keep_within_months,
+        })
+    }
+
+    fn has_keep_rules(&self) -> bool {
+        self.keep_last.is_some()
+            || self.keep_daily.is_some()
+            || self.keep_weekly.is_some()
+            || self.keep_monthly.is_some()
+            || self.keep_yearly.is_some()
+            || self.keep_within_months.is_some()
+    }
+}
+
 fn parse_months(s: &str) -> Result<i32> {
     let re = Regex::new(r"(?i)^\s*(\d+)\s*m(?:onths?)?\s*$")?;
     let caps = re
@@ -47,11 +176,303 @@ fn months_between(now: NaiveDate, then: NaiveDate) -> i32 {
     (now.year() - then.year()) * 12 + (now.month() as i32 - then.month() as i32)
 }
 
+/// Parsed form of `--older-than`: either a month count (the historical, always-available
+/// granularity) or a day count, which only makes sense when `--date-pattern daily` gives index
+/// dates actual day-of-month precision.
+enum Cutoff {
+    Months(i32),
+    Days(i64),
+}
+
+impl Cutoff {
+    /// Single letter used both for `--older-than` age logging and the dry-run age display, so
+    /// the two always agree on what unit the printed number is in.
+    fn unit(&self) -> &'static str {
+        match self {
+            Cutoff::Months(_) => "m",
+            Cutoff::Days(_) => "d",
+        }
+    }
+
+    /// Age of `then` relative to `now`, in this cutoff's unit.
+    fn age(&self, now: NaiveDate, then: NaiveDate) -> i64 {
+        match self {
+            Cutoff::Months(_) => months_between(now, then) as i64,
+            Cutoff::Days(_) => (now - then).num_days(),
+        }
+    }
+
+    fn is_met_by(&self, age: i64) -> bool {
+        match self {
+            Cutoff::Months(n) => age >= *n as i64,
+            Cutoff::Days(n) => age >= *n,
+        }
+    }
+}
+
+/// Parse `--older-than`, e.g. '25m' (months) or '10d' (days). Day granularity requires
+/// `--date-pattern daily`, since only then do parsed index dates carry real day-of-month info.
+fn parse_cutoff(s: &str, date_pattern: &DatePattern) -> Result<Cutoff> {
+    let re = Regex::new(r"(?i)^\s*(\d+)\s*(d(?:ays?)?|m(?:onths?)?)\s*$")?;
+    let caps = re
+        .captures(s)
+        .ok_or_else(|| anyhow!("Invalid --older-than value: '{s}'. Try '25m' or '10d'."))?;
+    let n: i64 = caps[1].parse()?;
+    if caps[2].to_ascii_lowercase().starts_with('d') {
+        if !matches!(date_pattern, DatePattern::Daily) {
+            return Err(anyhow!(
+                "--older-than='{s}' uses day granularity, which needs --date-pattern daily"
+            ));
+        }
+        Ok(Cutoff::Days(n))
+    } else {
+        Ok(Cutoff::Months(n.try_into().context("--older-than months value out of range")?))
+    }
+}
+
+/// Evaluate a keep/forget retention policy over `entries` (index name, parsed date).
+///
+/// Entries are walked newest-first, once per active rule, each maintaining its own
+/// "last kept bucket" and counter so e.g. `--keep-monthly N` means "keep the N most recent
+/// *distinct* months", not just the N most recent entries. `--keep-daily`/`--keep-weekly`
+/// bucket on the exact date/ISO week and are only meaningful with `--date-pattern daily`.
+/// Returns the forget set: names of indices that no rule chose to keep.
+fn apply_retention(mut entries: Vec<(String, NaiveDate)>, policy: &RetentionPolicy, now_first: NaiveDate) -> Vec<String> {
+    entries.sort_by_key(|(_, date)| std::cmp::Reverse(*date));
+
+    let mut kept = vec![false; entries.len()];
+
+    if let Some(n) = policy.keep_last {
+        for keep in kept.iter_mut().take(n) {
+            *keep = true;
+        }
+    }
+
+    if let Some(n) = policy.keep_daily {
+        let mut last_bucket: Option<NaiveDate> = None;
+        let mut count = 0usize;
+        for (i, (_, date)) in entries.iter().enumerate() {
+            if count >= n {
+                break;
+            }
+            if Some(*date) != last_bucket {
+                kept[i] = true;
+                last_bucket = Some(*date);
+                count += 1;
+            }
+        }
+    }
+
+    if let Some(n) = policy.keep_weekly {
+        let mut last_bucket: Option<(i32, u32)> = None;
+        let mut count = 0usize;
+        for (i, (_, date)) in entries.iter().enumerate() {
+            if count >= n {
+                break;
+            }
+            let iso = date.iso_week();
+            let bucket = (iso.year(), iso.week());
+            if Some(bucket) != last_bucket {
+                kept[i] = true;
+                last_bucket = Some(bucket);
+                count += 1;
+            }
+        }
+    }
+
+    if let Some(n) = policy.keep_monthly {
+        let mut last_bucket: Option<(i32, u32)> = None;
+        let mut count = 0usize;
+        for (i, (_, date)) in entries.iter().enumerate() {
+            let bucket = (date.year(), date.month());
+            if count >= n {
+                break;
+            }
+            if Some(bucket) != last_bucket {
+                kept[i] = true;
+                last_bucket = Some(bucket);
+                count += 1;
+            }
+        }
+    }
+
+    if let Some(n) = policy.keep_yearly {
+        let mut last_bucket: Option<i32> = None;
+        let mut count = 0usize;
+        for (i, (_, date)) in entries.iter().enumerate() {
+            let bucket = date.year();
+            if count >= n {
+                break;
+            }
+            if Some(bucket) != last_bucket {
+                kept[i] = true;
+                last_bucket = Some(bucket);
+                count += 1;
+            }
+        }
+    }
+
+    if let Some(months) = policy.keep_within_months {
+        for (i, (_, date)) in entries.iter().enumerate() {
+            if months_between(now_first, *date) < months {
+                kept[i] = true;
+            }
+        }
+    }
+
+    entries
+        .into_iter()
+        .zip(kept)
+        .filter(|(_, keep)| !*keep)
+        .map(|((index, _), _)| index)
+        .collect()
+}
+
+/// Extract the grouping key for an index name from an optional `--group-by` regex's first
+/// capture group. When no regex is given, every index shares a single group, preserving
+/// today's "pool everything" behavior. When a regex is given but doesn't match a particular
+/// index, returns `None` — the caller must treat that as an error rather than silently
+/// granting the index its own singleton group (see the `validate_group_by_regex` doc comment
+/// for why a singleton group is effectively permanent retention under the count-based rules).
+fn group_key(index: &str, group_re: &Option<Regex>) -> Option<String> {
+    match group_re {
+        Some(re) => re
+            .captures(index)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_string()),
+        None => Some("*".to_string()),
+    }
+}
+
+/// A `--group-by` regex with no capture group can't produce a grouping key, so `group_key`
+/// would return `None` for every index. Giving each such index its own group instead would make
+/// every group a single-element group, and a single-element group is always fully kept by any
+/// count-based `--keep-*` rule with N >= 1 (`--keep-within` still ages it out). So such a regex
+/// is rejected up front, before any index is fetched.
+fn validate_group_by_regex(re: &Regex) -> Result<()> {
+    if re.captures_len() <= 1 {
+        return Err(anyhow!(
+            "--group-by regex '{}' has no capture group; add one (e.g. '([a-z]+)-\\d{{4}}') to extract a grouping key",
+            re.as_str()
+        ));
+    }
+    Ok(())
+}
+
+/// Re-derive the parsed date for an index name already known to match `re`, honoring
+/// `date_pattern`'s capture-group layout. Used after retention has reduced a group down to
+/// names only, where a best-effort re-parse (vs. threading dates through) keeps the code simple.
+fn extract_date(idx: &str, re: &Regex, date_pattern: &DatePattern) -> Option<NaiveDate> {
+    let caps = re.captures(idx)?;
+    let y: i32 = caps[1].parse().ok()?;
+    let m: u32 = caps[2].parse().ok()?;
+    if !(1..=12).contains(&m) {
+        return None;
+    }
+    match date_pattern {
+        DatePattern::Monthly => NaiveDate::from_ymd_opt(y, m, 1),
+        DatePattern::Daily => {
+            let d: u32 = caps.get(3)?.as_str().parse().ok()?;
+            NaiveDate::from_ymd_opt(y, m, d)
+        }
+    }
+}
+
+/// Take and verify a snapshot of `indices` into `repo` before the caller proceeds to delete
+/// them, mirroring a backup-then-forget workflow. Blocks until Elasticsearch reports the
+/// snapshot finished (`wait_for_completion=true`) and errors out (without deleting anything)
+/// unless the snapshot state is `SUCCESS`.
+async fn take_snapshot(
+    client: &Client,
+    base: &Url,
+    repo: &str,
+    indices: &[String],
+    auth: Option<(&str, &str)>,
+    timeout: Duration,
+) -> Result<()> {
+    let snapshot_name = format!("es-retention-{}", Utc::now().format("%Y%m%dT%H%M%SZ"));
+
+    let mut url = base.clone();
+    url.set_path(&format!(
+        "_snapshot/{}/{}",
+        utf8_percent_encode(repo, NON_ALPHANUMERIC),
+        utf8_percent_encode(&snapshot_name, NON_ALPHANUMERIC)
+    ));
+    url.query_pairs_mut().append_pair("wait_for_completion", "true");
+
+    let body = serde_json::json!({ "indices": indices.join(",") });
+
+    let mut req = client.put(url).json(&body).timeout(timeout);
+    if let Some((u, p)) = auth {
+        req = req.basic_auth(u, Some(p));
+    }
+
+    info!("Taking snapshot '{}' of {} indices in repo '{}'…", snapshot_name, indices.len(), repo);
+    let resp = req.send().await?;
+    let status = resp.status();
+    let text = resp.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        return Err(anyhow!("Snapshot '{}' request failed: {} | {}", snapshot_name, status, text));
+    }
+
+    let parsed: SnapshotResponse = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse snapshot response: {text}"))?;
+    if parsed.snapshot.state != "SUCCESS" {
+        return Err(anyhow!(
+            "Snapshot '{}' did not complete successfully: state={}",
+            snapshot_name,
+            parsed.snapshot.state
+        ));
+    }
+
+    info!("Snapshot '{}' completed successfully.", snapshot_name);
+    Ok(())
+}
+
+/// Issue a single DELETE for `idx` and turn the result into a `DeleteOutcome`, logging along
+/// the way exactly as the previous serial loop did.
+async fn delete_one(
+    client: &Client,
+    base: &Url,
+    idx: String,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> DeleteOutcome {
+    let mut del_url = base.clone();
+    let path = utf8_percent_encode(&idx, NON_ALPHANUMERIC).to_string();
+    del_url.set_path(&path);
+
+    let mut req = client.delete(del_url);
+    if let (Some(u), Some(p)) = (username, password) {
+        req = req.basic_auth(u, Some(p));
+    }
+
+    match req.send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            if status.is_success() {
+                info!("DELETE {} -> {}", idx, status);
+                DeleteOutcome { index: idx, success: true, status: status.as_u16(), error: None }
+            } else {
+                error!("DELETE {} failed: {} | {}", idx, status, body);
+                DeleteOutcome { index: idx, success: false, status: status.as_u16(), error: Some(body) }
+            }
+        }
+        Err(e) => {
+            error!("DELETE {} failed: {e}", idx);
+            DeleteOutcome { index: idx, success: false, status: 0, error: Some(e.to_string()) }
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
         .with_target(false)
+        .with_writer(std::io::stderr)
         .compact()
         .init();
 
@@ -62,9 +483,23 @@ async fn main() -> Result<()> {
         return Err(anyhow!("Both --username and --password must be provided for basic auth."));
     }
 
-    let months_cutoff = parse_months(&args.older_than)
+    let policy = RetentionPolicy::from_args(&args)?;
+    let group_re = args
+        .group_by
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .context("Invalid --group-by regex")?;
+    if let Some(re) = &group_re {
+        validate_group_by_regex(re)?;
+    }
+    let cutoff = parse_cutoff(&args.older_than, &args.date_pattern)
         .with_context(|| format!("Failed to parse --older-than='{}'", args.older_than))?;
-    info!("Cutoff: indices older than or equal to {months_cutoff} months will be deleted.");
+    if policy.has_keep_rules() {
+        info!("Retention policy active: {:?}", policy);
+    } else {
+        info!("Cutoff: indices older than or equal to {} will be deleted.", args.older_than);
+    }
 
     let base = Url::parse(&args.url).context("Invalid --url")?;
     debug!("Base URL: {base}");
@@ -115,16 +550,25 @@ async fn main() -> Result<()> {
     });
     debug!("Sorted {} index names by YYYY-MM", items.len());
 
-    // Regex akceptující YYYY-MM i YYYY.MM
-    let re = Regex::new(&format!(
-        r#"^{}(\d{{4}})[\.-](\d{{2}})$"#,
-        regex::escape(&args.index_prefix)
-    ))?;
+    // Regex akceptující YYYY-MM i YYYY.MM (monthly), or YYYY-MM-DD / YYYY.MM.DD (daily).
+    // The lazy `.*?` tolerates an optional stream-name segment between the prefix and the
+    // date suffix (e.g. "<prefix>app-logs-2024-03"), which --group-by relies on to pull
+    // multiple streams out of one --index-prefix pool.
+    let re = match args.date_pattern {
+        DatePattern::Monthly => Regex::new(&format!(
+            r#"^{}.*?(\d{{4}})[\.-](\d{{2}})$"#,
+            regex::escape(&args.index_prefix)
+        ))?,
+        DatePattern::Daily => Regex::new(&format!(
+            r#"^{}.*?(\d{{4}})[\.-](\d{{2}})[\.-](\d{{2}})$"#,
+            regex::escape(&args.index_prefix)
+        ))?,
+    };
 
     let now = Utc::now().date_naive();
     let now_first = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
 
-    let mut targets: Vec<(String, i32)> = Vec::new(); // (index, age_months)
+    let mut matched: Vec<(String, NaiveDate)> = Vec::new();
     for it in items {
         if let Some(caps) = re.captures(&it.index) {
             let y: i32 = match caps[1].parse() {
@@ -135,20 +579,75 @@ async fn main() -> Result<()> {
             };
             if !(1..=12).contains(&m) { warn!("Skip {}: month out of range", it.index); continue; }
 
-            let then = NaiveDate::from_ymd_opt(y, m, 1).unwrap();
-            let age_months = months_between(now_first, then);
-            debug!("Index {} -> age {} months", it.index, age_months);
-
-            if age_months >= months_cutoff {
-                targets.push((it.index, age_months));
+            let then = match args.date_pattern {
+                DatePattern::Monthly => NaiveDate::from_ymd_opt(y, m, 1),
+                DatePattern::Daily => {
+                    let d: u32 = match caps[3].parse() {
+                        Ok(v) => v, Err(e) => { warn!("Skip {}: bad day: {e}", it.index); continue; }
+                    };
+                    NaiveDate::from_ymd_opt(y, m, d)
+                }
+            };
+            match then {
+                Some(date) => matched.push((it.index, date)),
+                None => warn!("Skip {}: invalid date", it.index),
             }
         } else {
             trace!("Index name did not match pattern, skipping: {}", it.index);
         }
     }
 
-    // NEW: seřadit kandidáty k mazání od nejstarších (největší age) po nejmladší
-    targets.sort_by(|a, b| a.1.cmp(&b.1)); // vzestupně dle age (nejstarší = nejvyšší age; pokud chceš opačně, použij b.1.cmp(&a.1))
+    let mut grouped: HashMap<String, Vec<(String, NaiveDate)>> = HashMap::new();
+    let mut ungrouped: Vec<String> = Vec::new();
+    for (idx, then) in matched {
+        match group_key(&idx, &group_re) {
+            Some(key) => grouped.entry(key).or_default().push((idx, then)),
+            None => ungrouped.push(idx),
+        }
+    }
+    if !ungrouped.is_empty() {
+        return Err(anyhow!(
+            "--group-by regex didn't capture a group from {} matched index(es): {}. Refusing to \
+             run rather than silently putting those indices in their own singleton group, which \
+             every count-based --keep-* rule keeps forever.",
+            ungrouped.len(),
+            ungrouped.join(", ")
+        ));
+    }
+
+    let evaluate_group = |entries: Vec<(String, NaiveDate)>| -> Vec<(String, i64)> {
+        if policy.has_keep_rules() {
+            apply_retention(entries, &policy, now_first)
+                .into_iter()
+                .map(|idx| {
+                    let then = extract_date(&idx, &re, &args.date_pattern).unwrap_or(now_first);
+                    (idx, cutoff.age(now, then))
+                })
+                .collect()
+        } else {
+            entries
+                .into_iter()
+                .filter_map(|(idx, then)| {
+                    let age = cutoff.age(now, then);
+                    debug!("Index {} -> age {}{}", idx, age, cutoff.unit());
+                    cutoff.is_met_by(age).then_some((idx, age))
+                })
+                .collect()
+        }
+    };
+
+    let mut group_keys: Vec<String> = grouped.keys().cloned().collect();
+    group_keys.sort();
+
+    let mut targets: Vec<(String, String, i64)> = Vec::new(); // (group, index, age)
+    for key in &group_keys {
+        let entries = grouped.remove(key).unwrap_or_default();
+        let mut group_targets = evaluate_group(entries);
+        group_targets.sort_by_key(|(_, age)| *age);
+        for (idx, age) in group_targets {
+            targets.push((key.clone(), idx, age));
+        }
+    }
 
     if targets.is_empty() {
         info!("Nothing to delete (0 indices match threshold).");
@@ -158,34 +657,199 @@ async fn main() -> Result<()> {
     let dryrun = !args.no_dryrun; // default true (dry-run), pokud uživatel zadá --no-dryrun => false
 
     if dryrun {
-        info!("Dryrun: would delete {} indices (oldest first):", targets.len());
-        for (t, age) in &targets {
-            info!("{t}  (age={}m)", age);
+        info!("Dryrun: would delete {} indices across {} group(s) (newest first within each group):", targets.len(), group_keys.len());
+        let mut current_group: Option<&str> = None;
+        for (group, t, age) in &targets {
+            if current_group != Some(group.as_str()) {
+                info!("Group '{group}':");
+                current_group = Some(group.as_str());
+            }
+            info!("  {t}  (age={}{})", age, cutoff.unit());
         }
         return Ok(());
     }
 
-    info!("Live: Deleting {} indices (oldest first)…", targets.len());
-    for (idx, _age) in targets {
-        let mut del_url = base.clone();
-        let path = utf8_percent_encode(&idx, NON_ALPHANUMERIC).to_string();
-        del_url.set_path(&path);
+    if let Some(repo) = args.snapshot_repo.as_deref() {
+        let auth = match (args.username.as_deref(), args.password.as_deref()) {
+            (Some(u), Some(p)) => Some((u, p)),
+            _ => None,
+        };
+        let index_names: Vec<String> = targets.iter().map(|(_, idx, _)| idx.clone()).collect();
+        let snapshot_timeout = Duration::from_secs(args.snapshot_timeout_secs);
+        take_snapshot(&client, &base, repo, &index_names, auth, snapshot_timeout).await?;
+    }
 
-        let mut req = client.delete(del_url);
-        if let (Some(u), Some(p)) = (args.username.as_ref(), args.password.as_ref()) {
-            req = req.basic_auth(u, Some(p));
-        }
-        let resp = req.send().await?;
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
+    let concurrency = args.concurrency.max(1);
+    info!("Live: Deleting {} indices with {concurrency} concurrent worker(s)…", targets.len());
 
-        if status.is_success() {
-            info!("DELETE {} -> {}", idx, status);
-        } else {
-            error!("DELETE {} failed: {} | {}", idx, status, body);
+    let results: Vec<DeleteOutcome> = stream::iter(targets.into_iter().map(|(_group, idx, _age)| {
+        let client = client.clone();
+        let base = base.clone();
+        let username = args.username.clone();
+        let password = args.password.clone();
+        async move { delete_one(&client, &base, idx, username.as_deref(), password.as_deref()).await }
+    }))
+    .buffer_unordered(concurrency)
+    .collect()
+    .await;
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - succeeded;
+
+    match args.output {
+        OutputFormat::Text => {
+            info!("Done: {succeeded} succeeded, {failed} failed (of {} total).", results.len());
+        }
+        OutputFormat::Json => {
+            let summary = serde_json::json!({
+                "succeeded": succeeded,
+                "failed": failed,
+                "total": results.len(),
+                "results": results,
+            });
+            println!("{}", serde_json::to_string_pretty(&summary)?);
         }
     }
 
+    if failed > 0 {
+        return Err(anyhow!("{failed} of {} index deletions failed", results.len()));
+    }
+
     Ok(())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn keep_monthly_keeps_one_per_distinct_month() {
+        let entries = vec![
+            ("idx-2024-03".to_string(), date(2024, 3, 1)),
+            ("idx-2024-02-b".to_string(), date(2024, 2, 1)),
+            ("idx-2024-02-a".to_string(), date(2024, 2, 1)),
+            ("idx-2024-01".to_string(), date(2024, 1, 1)),
+        ];
+        let policy = RetentionPolicy { keep_monthly: Some(2), ..Default::default() };
+
+        let forgotten = apply_retention(entries, &policy, date(2024, 3, 1));
+
+        // Newest two distinct months (March, February) are kept; within February only the
+        // newest entry counts towards the bucket, so the older February duplicate is forgotten
+        // alongside January.
+        assert_eq!(
+            forgotten,
+            vec!["idx-2024-02-a".to_string(), "idx-2024-01".to_string()]
+        );
+    }
+
+    #[test]
+    fn keep_weekly_buckets_by_iso_week() {
+        // 2024-01-01 and 2024-01-02 fall in ISO week 1; 2024-01-08 starts ISO week 2.
+        let entries = vec![
+            ("idx-jan-08".to_string(), date(2024, 1, 8)),
+            ("idx-jan-02".to_string(), date(2024, 1, 2)),
+            ("idx-jan-01".to_string(), date(2024, 1, 1)),
+        ];
+        let policy = RetentionPolicy { keep_weekly: Some(2), ..Default::default() };
+
+        let forgotten = apply_retention(entries, &policy, date(2024, 1, 8));
+
+        assert_eq!(forgotten, vec!["idx-jan-01".to_string()]);
+    }
+
+    #[test]
+    fn keep_rules_combine_with_or_semantics() {
+        // keep-last(1) keeps only the single newest entry; keep-monthly(2) independently keeps
+        // the newest entry of each of the two newest months. Both rules pick the same newest row here, but a
+        // second, older row should survive purely because keep-monthly reaches further back
+        // once a new month starts.
+        let entries = vec![
+            ("idx-2024-03".to_string(), date(2024, 3, 1)),
+            ("idx-2024-02".to_string(), date(2024, 2, 1)),
+            ("idx-2024-01".to_string(), date(2024, 1, 1)),
+        ];
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            keep_monthly: Some(2),
+            ..Default::default()
+        };
+
+        let forgotten = apply_retention(entries, &policy, date(2024, 3, 1));
+
+        // keep-last(1) keeps idx-2024-03 on its own; keep-monthly(2) additionally keeps
+        // idx-2024-02. Only idx-2024-01 matches neither rule.
+        assert_eq!(forgotten, vec!["idx-2024-01".to_string()]);
+    }
+
+    #[test]
+    fn group_key_falls_back_to_single_group_without_group_by() {
+        assert_eq!(group_key("app-logs-2024-03", &None), Some("*".to_string()));
+        assert_eq!(group_key("nginx-2024-03", &None), Some("*".to_string()));
+    }
+
+    #[test]
+    fn group_key_returns_none_when_regex_has_no_match() {
+        // A --group-by regex that simply doesn't match an index name must NOT fall back to
+        // grouping that index on its own (a singleton group is kept forever by any count-based
+        // --keep-* rule); the caller treats this None as a reason to fail the run instead.
+        let re = Regex::new(r"^svc-([a-z]+)-").unwrap();
+        assert_eq!(group_key("unrelated-index", &Some(re)), None);
+    }
+
+    #[test]
+    fn group_key_extracts_capture_group_when_regex_matches() {
+        let re = Regex::new(r"^svc-([a-z]+)-").unwrap();
+        assert_eq!(group_key("svc-app-2024-03", &Some(re)), Some("app".to_string()));
+    }
+
+    #[test]
+    fn validate_group_by_regex_rejects_regex_without_capture_group() {
+        let re = Regex::new(r"^svc-[a-z]+-\d{4}$").unwrap();
+        assert!(validate_group_by_regex(&re).is_err());
+    }
+
+    #[test]
+    fn validate_group_by_regex_accepts_regex_with_capture_group() {
+        let re = Regex::new(r"^svc-([a-z]+)-\d{4}$").unwrap();
+        assert!(validate_group_by_regex(&re).is_ok());
+    }
+
+    #[test]
+    fn parse_cutoff_rejects_day_granularity_under_monthly_pattern() {
+        assert!(parse_cutoff("10d", &DatePattern::Monthly).is_err());
+    }
+
+    #[test]
+    fn parse_cutoff_accepts_day_granularity_under_daily_pattern() {
+        let cutoff = parse_cutoff("10d", &DatePattern::Daily).unwrap();
+        assert_eq!(cutoff.age(date(2024, 1, 11), date(2024, 1, 1)), 10);
+        assert_eq!(cutoff.unit(), "d");
+    }
+
+    #[test]
+    fn parse_cutoff_months_is_available_under_either_pattern() {
+        assert!(parse_cutoff("25m", &DatePattern::Monthly).is_ok());
+        assert!(parse_cutoff("25m", &DatePattern::Daily).is_ok());
+    }
+
+    #[test]
+    fn retention_policy_rejects_keep_daily_without_daily_pattern() {
+        let args = Args::parse_from([
+            "es-retention", "--url", "http://x", "--keep-daily", "3",
+        ]);
+        assert!(RetentionPolicy::from_args(&args).is_err());
+    }
+
+    #[test]
+    fn retention_policy_accepts_keep_daily_with_daily_pattern() {
+        let args = Args::parse_from([
+            "es-retention", "--url", "http://x", "--keep-daily", "3", "--date-pattern", "daily",
+        ]);
+        assert!(RetentionPolicy::from_args(&args).is_ok());
+    }
+}